@@ -1,30 +1,174 @@
 use std::collections::HashMap;
-use std::fs::File;
+use std::fs::{self, File};
 use std::io;
+use std::path::Path;
 
+use regex::Regex;
 use serde::{Deserialize, Serialize};
 use serde_json::{from_reader, to_writer, to_writer_pretty};
 
 const DEFAULT_FILE_PATH: &str = "model.json";
-const INIT_RATING: f32 = 0.4;
+
+/// Default token-matching pattern: keeps digits and apostrophes, unlike the classic
+/// tokenizer which strips everything but letters, whitespace and `:`.
+const DEFAULT_TOKEN_PATTERN: &str = r"[a-z0-9']+";
 const SPAM_PROB_THRESHOLD: f32 = 0.8;
 
-#[derive(Debug, Default, Serialize, Deserialize)]
-struct Counter {
-    ham: u32,
-    spam: u32,
+/// Label used internally by the two-label spam/ham wrapper methods.
+const SPAM_LABEL: &str = "spam";
+/// Label used internally by the two-label spam/ham wrapper methods.
+const HAM_LABEL: &str = "ham";
+
+/// Default Robinson smoothing "strength" constant.
+const DEFAULT_ROBINSON_S: f32 = 1.0;
+/// Default Robinson smoothing assumed prior probability for a never-seen word.
+const DEFAULT_ROBINSON_X: f32 = 0.5;
+
+/// Default Fisher chi-squared index below which a message is deemed ham.
+const DEFAULT_HAM_CUTOFF: f32 = 0.2;
+/// Default Fisher chi-squared index above which a message is deemed spam.
+const DEFAULT_SPAM_CUTOFF: f32 = 0.8;
+
+/// Return the default label set used by models created before multi-class
+/// support was added, so existing serialized two-label models still load.
+fn default_labels() -> Vec<String> {
+    vec![HAM_LABEL.to_string(), SPAM_LABEL.to_string()]
 }
 
 /// A model.
-#[derive(Default, Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 struct Model {
-    token_table: HashMap<String, Counter>,
+    /// Per-token occurrence counts, keyed by label.
+    token_table: HashMap<String, HashMap<String, u32>>,
+    /// Number of trained messages, keyed by label.
+    #[serde(default)]
+    label_counts: HashMap<String, u32>,
+    /// Set of labels this model was trained with.
+    #[serde(default = "default_labels")]
+    labels: Vec<String>,
+}
+
+impl Default for Model {
+    fn default() -> Self {
+        Model {
+            token_table: HashMap::new(),
+            label_counts: HashMap::new(),
+            labels: default_labels(),
+        }
+    }
+}
+
+/// The outcome of classifying a message with [`Classifier::classify`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    Ham,
+    Unsure,
+    Spam,
+}
+
+/// Configuration for [`Tokenizer::Pattern`].
+#[derive(Debug, Clone)]
+pub struct TokenizerConfig {
+    /// Regex used to match tokens, compiled once at construction time. Defaults to
+    /// `[a-z0-9']+`, which keeps digits and contractions that the classic tokenizer
+    /// strips out.
+    pattern: Regex,
+    /// Minimum token length (in characters) to keep.
+    pub min_len: usize,
+    /// Emit word bigrams ("word1 word2") in addition to unigrams.
+    pub bigrams: bool,
+}
+
+impl TokenizerConfig {
+    /// Build a tokenizer configuration matching tokens with the given regex pattern.
+    ///
+    /// The pattern is compiled once, here, instead of on every [`Classifier::tokenize`]
+    /// call, and an invalid pattern is rejected immediately instead of being silently
+    /// replaced by the default.
+    ///
+    /// * `pattern` - &str. The regex pattern used to match tokens.
+    pub fn new(pattern: &str) -> Result<Self, regex::Error> {
+        Ok(TokenizerConfig {
+            pattern: Regex::new(pattern)?,
+            min_len: 1,
+            bigrams: false,
+        })
+    }
+
+    /// The regex pattern this configuration matches tokens with.
+    pub fn pattern(&self) -> &str {
+        self.pattern.as_str()
+    }
+}
+
+impl Default for TokenizerConfig {
+    fn default() -> Self {
+        TokenizerConfig::new(DEFAULT_TOKEN_PATTERN).expect("DEFAULT_TOKEN_PATTERN is a valid regex")
+    }
+}
+
+/// A caller-supplied tokenization function.
+type TokenizeFn = Box<dyn Fn(&str) -> Vec<String>>;
+
+/// Controls how a [`Classifier`] splits a message into tokens.
+#[derive(Default)]
+pub enum Tokenizer {
+    /// The original tokenizer: strip every character except letters, whitespace and
+    /// `:`, and keep only words longer than 2 bytes. Default, for backward compatibility with
+    /// existing serialized models.
+    #[default]
+    Classic,
+    /// Match tokens with a configurable regex, optionally keeping digits,
+    /// contractions and bigrams.
+    Pattern(TokenizerConfig),
+    /// Delegate tokenization entirely to a caller-supplied function.
+    Custom(TokenizeFn),
+}
+
+impl std::fmt::Debug for Tokenizer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Tokenizer::Classic => write!(f, "Tokenizer::Classic"),
+            Tokenizer::Pattern(config) => {
+                f.debug_tuple("Tokenizer::Pattern").field(config).finish()
+            }
+            Tokenizer::Custom(_) => write!(f, "Tokenizer::Custom(..)"),
+        }
+    }
 }
 
 /// A bayesian spam classifier.
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub struct Classifier {
     model: Model,
+    /// Robinson smoothing "strength" constant: how much weight is given to `x`
+    /// relative to the evidence gathered for a given word.
+    pub s: f32,
+    /// Robinson smoothing assumed prior probability for a word never seen before.
+    pub x: f32,
+    /// Fisher chi-squared index below which [`Classifier::classify`] returns `Verdict::Ham`.
+    pub ham_cutoff: f32,
+    /// Fisher chi-squared index above which [`Classifier::classify`] returns `Verdict::Spam`.
+    pub spam_cutoff: f32,
+    /// Opt-in: keep only the 10 smallest and 10 largest word ratings before scoring,
+    /// as `score` used to do before it moved to log-space combining.
+    pub truncate_ratings: bool,
+    /// Controls how messages are split into tokens before training or scoring.
+    pub tokenizer: Tokenizer,
+}
+
+impl Default for Classifier {
+    fn default() -> Self {
+        Classifier {
+            model: Model::default(),
+            s: DEFAULT_ROBINSON_S,
+            x: DEFAULT_ROBINSON_X,
+            ham_cutoff: DEFAULT_HAM_CUTOFF,
+            spam_cutoff: DEFAULT_SPAM_CUTOFF,
+            truncate_ratings: false,
+            tokenizer: Tokenizer::default(),
+        }
+    }
 }
 
 impl Model {
@@ -62,6 +206,20 @@ impl Classifier {
         Default::default()
     }
 
+    /// Build a new `Classifier` for an arbitrary set of labeled categories,
+    /// instead of the default two-label spam/ham model.
+    ///
+    /// * `labels` - &[&str]. The labels the classifier will be trained with.
+    pub fn with_labels(labels: &[&str]) -> Self {
+        Classifier {
+            model: Model {
+                labels: labels.iter().map(|label| label.to_string()).collect(),
+                ..Model::default()
+            },
+            ..Default::default()
+        }
+    }
+
     /// Build a new `Classifier` with a pre-trained model.
     ///
     /// * `file` - File. The file to read the pre-trained model from.
@@ -69,6 +227,7 @@ impl Classifier {
         match Model::new_from_pre_trained(file) {
             Ok(pre_trained_model) => Ok(Classifier {
                 model: pre_trained_model,
+                ..Default::default()
             }),
             Err(e) => Err(e),
         }
@@ -90,6 +249,42 @@ impl Classifier {
         .collect()
     }
 
+    /// Match tokens in the message with `config.pattern`, optionally emitting bigrams.
+    ///
+    /// * `msg` - String. Represents the message.
+    /// * `config` - TokenizerConfig. The tokenizer configuration to apply.
+    fn tokenize_with_pattern(msg: &str, config: &TokenizerConfig) -> Vec<String> {
+        let lower = msg.to_lowercase();
+
+        let unigrams: Vec<String> = config
+            .pattern
+            .find_iter(&lower)
+            .map(|m| m.as_str().to_string())
+            .filter(|token| token.chars().count() >= config.min_len)
+            .collect();
+
+        if !config.bigrams {
+            return unigrams;
+        }
+
+        let bigrams = unigrams
+            .windows(2)
+            .map(|pair| format!("{} {}", pair[0], pair[1]));
+
+        unigrams.iter().cloned().chain(bigrams).collect()
+    }
+
+    /// Split a message into tokens, according to `self.tokenizer`.
+    ///
+    /// * `msg` - String. Represents the message.
+    fn tokenize(&self, msg: &str) -> Vec<String> {
+        match &self.tokenizer {
+            Tokenizer::Classic => Self::load_word_list(msg),
+            Tokenizer::Pattern(config) => Self::tokenize_with_pattern(msg, config),
+            Tokenizer::Custom(tokenize) => tokenize(msg),
+        }
+    }
+
     /// Save the model into the given file.
     ///
     /// * `file` - File. The file to write to.
@@ -100,96 +295,241 @@ impl Classifier {
         Ok(())
     }
 
+    /// Train the model of the classifier with a message labeled with an arbitrary category.
+    ///
+    /// * `msg` - String. Represents the message.
+    /// * `label` - String. The category the message belongs to.
+    pub fn train(&mut self, msg: &str, label: &str) {
+        *self
+            .model
+            .label_counts
+            .entry(label.to_string())
+            .or_default() += 1;
+
+        for word in self.tokenize(msg) {
+            let counts = self.model.token_table.entry(word).or_default();
+            *counts.entry(label.to_string()).or_default() += 1;
+        }
+    }
+
     /// Train the model of the classifier with a spam.
     ///
     /// * `msg` - String. Represents the spam message.
     pub fn train_spam(&mut self, msg: &str) {
-        for word in Self::load_word_list(msg) {
-            let counter = self.model.token_table.entry(word).or_default();
-            counter.spam += 1;
-        }
+        self.train(msg, SPAM_LABEL);
     }
 
     /// Train the model of the classifier with a ham.
     ///
     /// * `msg` - String. Represents the ham message.
     pub fn train_ham(&mut self, msg: &str) {
-        for word in Self::load_word_list(msg) {
-            let counter = self.model.token_table.entry(word).or_default();
-            counter.ham += 1;
+        self.train(msg, HAM_LABEL);
+    }
+
+    /// Untrain the model of the classifier with a message labeled with an arbitrary category,
+    /// reverting a previous call to `train` with the same message and label.
+    ///
+    /// Counters are decremented with saturation at 0, and a token's entry for a label is
+    /// dropped once it reaches 0; the token itself is dropped from the table once no label
+    /// references it anymore.
+    ///
+    /// * `msg` - String. Represents the message.
+    /// * `label` - String. The category the message belongs to.
+    pub fn untrain(&mut self, msg: &str, label: &str) {
+        if let Some(count) = self.model.label_counts.get_mut(label) {
+            *count = count.saturating_sub(1);
+        }
+
+        for word in self.tokenize(msg) {
+            if let Some(counts) = self.model.token_table.get_mut(&word) {
+                if let Some(count) = counts.get_mut(label) {
+                    *count = count.saturating_sub(1);
+                    if *count == 0 {
+                        counts.remove(label);
+                    }
+                }
+
+                if counts.is_empty() {
+                    self.model.token_table.remove(&word);
+                }
+            }
         }
     }
 
+    /// Untrain the model of the classifier with a spam, reverting a previous call to
+    /// `train_spam` with the same message.
+    ///
+    /// * `msg` - String. Represents the spam message.
+    pub fn untrain_spam(&mut self, msg: &str) {
+        self.untrain(msg, SPAM_LABEL);
+    }
+
+    /// Untrain the model of the classifier with a ham, reverting a previous call to
+    /// `train_ham` with the same message.
+    ///
+    /// * `msg` - String. Represents the ham message.
+    pub fn untrain_ham(&mut self, msg: &str) {
+        self.untrain(msg, HAM_LABEL);
+    }
+
+    /// Train the model of the classifier with every file in the given directory as spam.
+    ///
+    /// * `dir` - Path. The directory containing spam message files.
+    pub fn train_spam_dir<P: AsRef<Path>>(&mut self, dir: P) -> Result<(), io::Error> {
+        self.train_dir(dir, SPAM_LABEL)
+    }
+
+    /// Train the model of the classifier with every file in the given directory as ham.
+    ///
+    /// * `dir` - Path. The directory containing ham message files.
+    pub fn train_ham_dir<P: AsRef<Path>>(&mut self, dir: P) -> Result<(), io::Error> {
+        self.train_dir(dir, HAM_LABEL)
+    }
+
+    /// Train the model of the classifier with every file in the given directory,
+    /// labeled with the given category.
+    ///
+    /// * `dir` - Path. The directory containing message files.
+    /// * `label` - String. The category the messages belong to.
+    fn train_dir<P: AsRef<Path>>(&mut self, dir: P, label: &str) -> Result<(), io::Error> {
+        for msg in read_dir_messages(dir)? {
+            self.train(&msg, label);
+        }
+
+        Ok(())
+    }
+
+    /// Return the total number of occurrences of the given label in the token table.
+    fn label_total_count(&self, label: &str) -> u32 {
+        self.model
+            .token_table
+            .values()
+            .map(|counts| counts.get(label).copied().unwrap_or(0))
+            .sum()
+    }
+
     /// Return the total number of spam in token table.
     fn spam_total_count(&self) -> u32 {
-        self.model.token_table.values().map(|x| x.spam).sum()
+        self.label_total_count(SPAM_LABEL)
     }
 
     /// Return the total number of ham in token table.
     fn ham_total_count(&self) -> u32 {
-        self.model.token_table.values().map(|x| x.ham).sum()
+        self.label_total_count(HAM_LABEL)
     }
 
-    /// Calculate and return for each word the probability that it is part of a spam.
+    /// Predict the most probable label for the message, using multinomial naive Bayes
+    /// with additive (Laplace) smoothing over the per-label vocabulary.
+    ///
+    /// * `msg` - String. Represents the message to classify.
+    pub fn predict(&self, msg: &str) -> String {
+        let words = self.tokenize(msg);
+        let vocab_size = self.model.token_table.len().max(1) as f64;
+        let total_docs: u32 = self.model.label_counts.values().sum();
+
+        self.model
+            .labels
+            .iter()
+            .map(|label| {
+                let label_docs = *self.model.label_counts.get(label).unwrap_or(&0);
+                let prior = if total_docs > 0 {
+                    label_docs as f64 / total_docs as f64
+                } else {
+                    1.0 / self.model.labels.len() as f64
+                };
+                let label_total = self.label_total_count(label) as f64;
+
+                // ln P(c) + Σ_w ln P(w|c), with Laplace smoothing over the vocabulary
+                let log_posterior = prior.ln()
+                    + words
+                        .iter()
+                        .map(|word| {
+                            let count = self
+                                .model
+                                .token_table
+                                .get(word)
+                                .and_then(|counts| counts.get(label))
+                                .copied()
+                                .unwrap_or(0) as f64;
+                            ((count + 1.0) / (label_total + vocab_size)).ln()
+                        })
+                        .sum::<f64>();
+
+                (label.clone(), log_posterior)
+            })
+            .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap())
+            .map(|(label, _)| label)
+            .unwrap_or_default()
+    }
+
+    /// Calculate and return for each word the probability that it is part of a spam,
+    /// using Robinson's smoothed estimate of word spamminess.
     ///
     /// * `msg` - String. Represents the message to score.
     fn rate_words(&self, msg: &str) -> Vec<f32> {
-        Self::load_word_list(msg)
+        self.tokenize(msg)
             .into_iter()
-            .map(|word| {
-                // If word was previously added in the model
-                if let Some(counter) = self.model.token_table.get(&word) {
-                    // If the word has only been part of spam messages,
-                    // assign it a probability of 0.99 to be part of a spam
-                    if counter.spam > 0 && counter.ham == 0 {
-                        return 0.99;
-                    // If the word has only been part of ham messages,
-                    // assign it a probability of 0.01 to be part of a spam
-                    } else if counter.spam == 0 && counter.ham > 0 {
-                        return 0.01;
-                    // If the word has been part of both spam and ham messages,
-                    // calculate the probability to be part of a spam
-                    } else if self.spam_total_count() > 0 && self.ham_total_count() > 0 {
-                        let ham_prob = (counter.ham as f32) / (self.ham_total_count() as f32);
-                        let spam_prob = (counter.spam as f32) / (self.spam_total_count() as f32);
-                        return (spam_prob / (ham_prob + spam_prob)).max(0.01);
-                    }
-                }
-                // If word was never added to the model,
-                // assign it an initial probability to be part of a spam
-                INIT_RATING
-            })
+            .map(|word| self.rate_word(&word))
             .collect()
     }
 
+    /// Calculate the Robinson-smoothed probability that a single word is part of a spam.
+    ///
+    /// * `word` - String. The word to rate.
+    fn rate_word(&self, word: &str) -> f32 {
+        // If the word was never added to the model,
+        // assume it follows the assumed prior probability `x`
+        let counts = match self.model.token_table.get(word) {
+            Some(counts) => counts,
+            None => return self.x,
+        };
+
+        let spam_count = counts.get(SPAM_LABEL).copied().unwrap_or(0);
+        let ham_count = counts.get(HAM_LABEL).copied().unwrap_or(0);
+
+        // Number of times the word has been seen, in spam and ham together
+        let n = (spam_count + ham_count) as f32;
+        if n == 0.0 {
+            return self.x;
+        }
+
+        // Raw spamminess of the word, relative to the rest of the corpus
+        let spam_prob = (spam_count as f32) / (self.spam_total_count().max(1) as f32);
+        let ham_prob = (ham_count as f32) / (self.ham_total_count().max(1) as f32);
+        let p_raw = spam_prob / (spam_prob + ham_prob);
+
+        // Pull `p_raw` toward the prior `x`, weighted by how much evidence `n` we have
+        let f = (self.s * self.x + n * p_raw) / (self.s + n);
+
+        // Keep a final floor so a word's rating is never exactly zero
+        f.max(0.01)
+    }
+
     /// Calculate and return the spam score of the message.
     /// The higher the score, the stronger the liklihood that the message is a spam is.
     ///
     /// * `msg` - String. Represents the message to score.
     pub fn score(&self, msg: &str) -> f32 {
         // Calculate for each word the probability that it is part of a spam
-        let ratings = self.rate_words(msg);
+        let mut ratings = self.rate_words(msg);
+        if ratings.is_empty() {
+            return 0.0;
+        }
 
-        // If there are no ratings, return a score of 0
-        // If there are more than 20 ratings, keep only the 10 first
-        // and 10 last ratings to calculate a score
-        // In all other cases, keep ratings to calculate a score
-        let ratings = match ratings.len() {
-            0 => return 0.0,
-            x if x > 20 => {
-                let length = ratings.len();
-                let mut ratings = ratings;
-                ratings.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap());
-                [&ratings[..10], &ratings[length - 10..]].concat()
-            }
-            _ => ratings,
-        };
+        // Only kept for callers who opt into the old truncation behavior;
+        // log-space combining below no longer needs it to avoid underflow
+        if self.truncate_ratings && ratings.len() > 20 {
+            let length = ratings.len();
+            ratings.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap());
+            ratings = [&ratings[..10], &ratings[length - 10..]].concat();
+        }
+
+        // Combine ratings in log space (as `f64`, for precision) instead of multiplying
+        // raw probabilities together, which underflows to 0.0 on longer messages
+        let log_p: f64 = ratings.iter().map(|&p| (p as f64).ln()).sum();
+        let log_q: f64 = ratings.iter().map(|&p| (1.0 - p as f64).ln()).sum();
 
-        // Calculate the final score of the message to be a spam,
-        // by multiplying all word ratings together
-        let product: f32 = ratings.iter().product();
-        let alt_product: f32 = ratings.iter().map(|x| 1.0 - x).product();
-        product / (product + alt_product)
+        (1.0 / (1.0 + (log_q - log_p).exp())) as f32
     }
 
     /// Identify whether the message is a spam or not.
@@ -198,6 +538,55 @@ impl Classifier {
     pub fn identify(&self, msg: &str) -> bool {
         self.score(msg) > SPAM_PROB_THRESHOLD
     }
+
+    /// Classify the message as ham, spam, or unsure, using Fisher's chi-squared method
+    /// of combining the per-word spam probabilities.
+    ///
+    /// * `msg` - String. Represents the message to classify.
+    pub fn classify(&self, msg: &str) -> Verdict {
+        let ratings = self.rate_words(msg);
+        let n = ratings.len();
+        if n == 0 {
+            return Verdict::Ham;
+        }
+
+        // Combine the individual word probabilities into two chi-squared statistics:
+        // `h` is strong (near 1) when the evidence points to ham, `s` is strong (near 1)
+        // when the evidence points to spam
+        let log_p: f64 = ratings.iter().map(|&p| (p as f64).ln()).sum();
+        let log_q: f64 = ratings.iter().map(|&p| (1.0 - p as f64).ln()).sum();
+        let h = Self::chi2_q(-2.0 * log_q, 2 * n);
+        let s = Self::chi2_q(-2.0 * log_p, 2 * n);
+
+        // Combine `h` and `s` into a single index in `[0, 1]`
+        let index = ((s - h + 1.0) / 2.0) as f32;
+
+        if index <= self.ham_cutoff {
+            Verdict::Ham
+        } else if index >= self.spam_cutoff {
+            Verdict::Spam
+        } else {
+            Verdict::Unsure
+        }
+    }
+
+    /// Calculate the survival function of the chi-squared distribution for an even
+    /// number of degrees of freedom, using the closed-form recurrence from spambayes.
+    ///
+    /// * `x` - f64. The chi-squared test statistic.
+    /// * `df` - usize. The (even) number of degrees of freedom.
+    fn chi2_q(x: f64, df: usize) -> f64 {
+        let m = x / 2.0;
+        let mut term = (-m).exp();
+        let mut sum = term;
+
+        for i in 1..(df / 2) {
+            term *= m / i as f64;
+            sum += term;
+        }
+
+        sum.min(1.0)
+    }
 }
 
 /// Calculate and return the spam score of the message, based on the pre-trained model.
@@ -226,6 +615,127 @@ pub fn identify(msg: &str) -> Result<bool, io::Error> {
     Ok(is_spam)
 }
 
+/// Read the contents of every file in the given directory.
+///
+/// Files are decoded lossily, since real-world message corpora (e.g. Enron-Spam)
+/// mix UTF-8 with Latin-1 and other legacy encodings; a single malformed file
+/// should not throw away every other message already read from the directory.
+///
+/// * `dir` - Path. The directory to read message files from.
+fn read_dir_messages<P: AsRef<Path>>(dir: P) -> Result<Vec<String>, io::Error> {
+    let mut messages = Vec::new();
+
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_file() {
+            let bytes = fs::read(&path)
+                .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path.display(), e)))?;
+            messages.push(String::from_utf8_lossy(&bytes).into_owned());
+        }
+    }
+
+    Ok(messages)
+}
+
+/// Confusion-matrix based metrics produced by [`evaluate`] and [`evaluate_dirs`].
+#[derive(Debug, Default, PartialEq)]
+pub struct Metrics {
+    pub true_positives: u32,
+    pub false_positives: u32,
+    pub true_negatives: u32,
+    pub false_negatives: u32,
+}
+
+impl Metrics {
+    /// Fraction of messages identified as spam that were actually spam.
+    pub fn precision(&self) -> f32 {
+        let predicted_spam = self.true_positives + self.false_positives;
+        if predicted_spam == 0 {
+            return 0.0;
+        }
+
+        self.true_positives as f32 / predicted_spam as f32
+    }
+
+    /// Fraction of actual spam messages that were identified as spam.
+    pub fn recall(&self) -> f32 {
+        let actual_spam = self.true_positives + self.false_negatives;
+        if actual_spam == 0 {
+            return 0.0;
+        }
+
+        self.true_positives as f32 / actual_spam as f32
+    }
+
+    /// Harmonic mean of precision and recall.
+    pub fn f1(&self) -> f32 {
+        let (precision, recall) = (self.precision(), self.recall());
+        if precision + recall == 0.0 {
+            return 0.0;
+        }
+
+        2.0 * precision * recall / (precision + recall)
+    }
+
+    /// Fraction of messages (spam or ham) that were correctly identified.
+    pub fn accuracy(&self) -> f32 {
+        let total =
+            self.true_positives + self.false_positives + self.true_negatives + self.false_negatives;
+        if total == 0 {
+            return 0.0;
+        }
+
+        (self.true_positives + self.true_negatives) as f32 / total as f32
+    }
+}
+
+/// Evaluate the classifier against a labeled set of messages, returning precision,
+/// recall, F1 and accuracy alongside the underlying confusion matrix.
+///
+/// * `classifier` - &Classifier. The classifier to evaluate.
+/// * `examples` - Iterator<Item = (String, bool)>. Labeled messages, where the boolean
+///   indicates whether the message is actually spam.
+pub fn evaluate(
+    classifier: &Classifier,
+    examples: impl Iterator<Item = (String, bool)>,
+) -> Metrics {
+    let mut metrics = Metrics::default();
+
+    for (msg, is_spam) in examples {
+        match (classifier.identify(&msg), is_spam) {
+            (true, true) => metrics.true_positives += 1,
+            (true, false) => metrics.false_positives += 1,
+            (false, true) => metrics.false_negatives += 1,
+            (false, false) => metrics.true_negatives += 1,
+        }
+    }
+
+    metrics
+}
+
+/// Evaluate the classifier against labeled spam and ham directories.
+///
+/// * `classifier` - &Classifier. The classifier to evaluate.
+/// * `spam_dir` - Path. A directory of held-out spam message files.
+/// * `ham_dir` - Path. A directory of held-out ham message files.
+pub fn evaluate_dirs<P: AsRef<Path>>(
+    classifier: &Classifier,
+    spam_dir: P,
+    ham_dir: P,
+) -> Result<Metrics, io::Error> {
+    let mut examples: Vec<(String, bool)> = read_dir_messages(spam_dir)?
+        .into_iter()
+        .map(|msg| (msg, true))
+        .collect();
+    examples.extend(
+        read_dir_messages(ham_dir)?
+            .into_iter()
+            .map(|msg| (msg, false)),
+    );
+
+    Ok(evaluate(classifier, examples.into_iter()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -235,13 +745,15 @@ mod tests {
         // Create a new classifier with an empty model
         let mut classifier = Classifier::new();
 
-        // Train the model with a new spam example
-        let spam = "Don't forget our special promotion: -30% on men shoes, only today!";
-        classifier.train_spam(spam);
+        // Train the model with a few spam examples
+        classifier.train_spam("Don't forget our special promotion: -30% on men shoes, only today!");
+        classifier.train_spam("Buy cheap viagra now, special promotion, limited offer!");
+        classifier.train_spam("Win a free iphone now, click this link to claim your prize!");
 
-        // Train the model with a new ham example
-        let ham = "Hi Bob, don't forget our meeting today at 4pm.";
-        classifier.train_ham(ham);
+        // Train the model with a few ham examples
+        classifier.train_ham("Hi Bob, don't forget our meeting today at 4pm.");
+        classifier.train_ham("Can you send me the report before the end of the day?");
+        classifier.train_ham("Thanks for your help yesterday, see you at the meeting.");
 
         // Identify a typical spam message
         let spam = "Lose up to 19% weight. Special promotion on our new weightloss.";
@@ -254,6 +766,266 @@ mod tests {
         assert!(!is_spam);
     }
 
+    #[test]
+    fn test_rate_word_pulls_single_occurrence_toward_prior() {
+        // Create a new classifier and train each word on a single occurrence
+        let mut classifier = Classifier::new();
+        classifier.train_spam("viagra");
+        classifier.train_ham("lunch");
+
+        // Before Robinson smoothing, a word seen in only one category would rate at
+        // the hardcoded extremes of 0.99/0.01. With smoothing, a single occurrence is
+        // weak evidence, so the rating should sit roughly halfway between that raw
+        // frequency and the prior `x`, not snap to the extreme.
+        let spam_word_rating = classifier.rate_word("viagra");
+        let ham_word_rating = classifier.rate_word("lunch");
+
+        assert!((spam_word_rating - 0.75).abs() < 1e-6);
+        assert!((ham_word_rating - 0.25).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_untrain() {
+        // Create a new classifier with an empty model
+        let mut classifier = Classifier::new();
+
+        let spam = "Buy cheap viagra now, special promotion, limited offer!";
+        classifier.train_spam(spam);
+        classifier.train_spam("Win a free iphone now, click this link to claim your prize!");
+        classifier.train_ham("Hi Bob, don't forget our meeting today at 4pm.");
+
+        assert!(classifier.model.token_table.contains_key("viagra"));
+        assert_eq!(*classifier.model.label_counts.get(SPAM_LABEL).unwrap(), 2);
+
+        // Reclassifying a message should remove its tokens from the spam side of the table
+        classifier.untrain_spam(spam);
+
+        assert_eq!(*classifier.model.label_counts.get(SPAM_LABEL).unwrap(), 1);
+        assert!(!classifier.model.token_table.contains_key("viagra"));
+
+        // Untraining uses saturating subtraction; it must never panic or wrap around
+        // when called more times than the message was trained
+        classifier.untrain_spam(spam);
+        assert_eq!(*classifier.model.label_counts.get(SPAM_LABEL).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_score_long_message() {
+        // Create a new classifier with an empty model
+        let mut classifier = Classifier::new();
+
+        // Train the model with a few spam examples
+        classifier.train_spam("Don't forget our special promotion: -30% on men shoes, only today!");
+        classifier.train_spam("Buy cheap viagra now, special promotion, limited offer!");
+        classifier.train_spam("Win a free iphone now, click this link to claim your prize!");
+        classifier.train_ham("Hi Bob, don't forget our meeting today at 4pm.");
+
+        // A long message with many spammy words used to underflow to a score of 0.0
+        // once it multiplied through more than 20 ratings; combining in log space
+        // should keep the score meaningful instead
+        let long_spam = "buy cheap viagra now claim your free prize limited offer \
+                          special promotion win a lottery act now click this link \
+                          no prescription needed congratulations you have won"
+            .repeat(5);
+        assert!(classifier.score(&long_spam) > 0.9);
+    }
+
+    #[test]
+    fn test_classify() {
+        // Create a new classifier with an empty model
+        let mut classifier = Classifier::new();
+
+        // Train the model with a few spam examples
+        classifier.train_spam("Don't forget our special promotion: -30% on men shoes, only today!");
+        classifier.train_spam("Buy cheap viagra now, special promotion, limited offer!");
+        classifier.train_spam("Win a free iphone now, click this link to claim your prize!");
+        classifier.train_spam("Cheap meds online no prescription needed, buy now!");
+        classifier.train_spam("Congratulations you have won a lottery, claim your prize today!");
+
+        // Train the model with a few ham examples
+        classifier.train_ham("Hi Bob, don't forget our meeting today at 4pm.");
+        classifier.train_ham("Can you send me the report before the end of the day?");
+        classifier.train_ham("Thanks for your help yesterday, see you at the meeting.");
+        classifier.train_ham("Let's grab lunch tomorrow if you are free.");
+        classifier.train_ham("The project deadline has been moved to next Friday.");
+
+        // A message with a lot of strong spam evidence should be confidently classified as spam
+        let spam = "Buy cheap viagra now, claim your free prize, limited offer, special promotion, \
+                     win a lottery, act now, click this link, no prescription needed, congratulations you have won!";
+        assert_eq!(classifier.classify(spam), Verdict::Spam);
+
+        // A message with a lot of strong ham evidence should be confidently classified as ham
+        let ham = "Hi Bob, thanks for your help yesterday, can you send me the report before our \
+                    meeting, let's grab lunch tomorrow and discuss the project deadline.";
+        assert_eq!(classifier.classify(ham), Verdict::Ham);
+
+        // An empty message has no tokens to weigh and is classified as ham
+        assert_eq!(classifier.classify(""), Verdict::Ham);
+    }
+
+    #[test]
+    fn test_tokenizer_pattern_keeps_digits_and_bigrams() {
+        let mut classifier = Classifier::new();
+        classifier.tokenizer = Tokenizer::Pattern(TokenizerConfig {
+            bigrams: true,
+            ..TokenizerConfig::default()
+        });
+
+        classifier.train_spam("v1agra 19% special promotion");
+
+        let counts = classifier
+            .model
+            .token_table
+            .get("v1agra")
+            .expect("digit-bearing token should have been kept");
+        assert_eq!(counts.get(SPAM_LABEL), Some(&1));
+
+        assert!(classifier
+            .model
+            .token_table
+            .contains_key("special promotion"));
+    }
+
+    #[test]
+    fn test_tokenizer_config_rejects_invalid_pattern() {
+        // An invalid regex must be reported at construction time, not silently
+        // replaced by the default pattern deep inside the tokenizing hot path
+        assert!(TokenizerConfig::new("[invalid(").is_err());
+    }
+
+    #[test]
+    fn test_tokenizer_custom() {
+        let mut classifier = Classifier::new();
+        classifier.tokenizer = Tokenizer::Custom(Box::new(|msg: &str| {
+            msg.split(',').map(|s| s.trim().to_lowercase()).collect()
+        }));
+
+        classifier.train_spam("Free Money, Act Now");
+
+        assert!(classifier.model.token_table.contains_key("free money"));
+        assert!(classifier.model.token_table.contains_key("act now"));
+    }
+
+    #[test]
+    fn test_train_spam_dir_and_evaluate() {
+        // Write a handful of spam and ham messages to a scratch directory
+        let dir = std::env::temp_dir().join("bayespam_test_train_spam_dir_and_evaluate");
+        let spam_dir = dir.join("spam");
+        let ham_dir = dir.join("ham");
+        fs::create_dir_all(&spam_dir).unwrap();
+        fs::create_dir_all(&ham_dir).unwrap();
+
+        fs::write(
+            spam_dir.join("1.txt"),
+            "Don't forget our special promotion: -30% on men shoes, only today!",
+        )
+        .unwrap();
+        fs::write(
+            spam_dir.join("2.txt"),
+            "Buy cheap viagra now, special promotion, limited offer!",
+        )
+        .unwrap();
+        fs::write(
+            ham_dir.join("1.txt"),
+            "Hi Bob, don't forget our meeting today at 4pm.",
+        )
+        .unwrap();
+        fs::write(
+            ham_dir.join("2.txt"),
+            "Can you send me the report before the end of the day?",
+        )
+        .unwrap();
+
+        // Train a classifier from the directories
+        let mut classifier = Classifier::new();
+        classifier.train_spam_dir(&spam_dir).unwrap();
+        classifier.train_ham_dir(&ham_dir).unwrap();
+
+        // Evaluate the classifier against the same directories it was trained on:
+        // it should recognize every message it was just trained with
+        let metrics = evaluate_dirs(&classifier, &spam_dir, &ham_dir).unwrap();
+        assert_eq!(metrics.true_positives, 2);
+        assert_eq!(metrics.true_negatives, 2);
+        assert_eq!(metrics.false_positives, 0);
+        assert_eq!(metrics.false_negatives, 0);
+        assert_eq!(metrics.accuracy(), 1.0);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_evaluate() {
+        // Create a classifier whose model perfectly separates the two examples below
+        let mut classifier = Classifier::new();
+        classifier.train_spam("Buy cheap viagra now, special promotion, limited offer!");
+        classifier.train_ham("Hi Bob, don't forget our meeting today at 4pm.");
+
+        let examples = vec![
+            (
+                "Buy cheap viagra now, special promotion, limited offer!".to_string(),
+                true,
+            ),
+            (
+                "Hi Bob, don't forget our meeting today at 4pm.".to_string(),
+                false,
+            ),
+        ];
+        let metrics = evaluate(&classifier, examples.into_iter());
+
+        assert_eq!(metrics.true_positives, 1);
+        assert_eq!(metrics.true_negatives, 1);
+        assert_eq!(metrics.false_positives, 0);
+        assert_eq!(metrics.false_negatives, 0);
+        assert_eq!(metrics.precision(), 1.0);
+        assert_eq!(metrics.recall(), 1.0);
+        assert_eq!(metrics.f1(), 1.0);
+        assert_eq!(metrics.accuracy(), 1.0);
+    }
+
+    #[test]
+    fn test_with_labels() {
+        // Create a new classifier for three arbitrary categories
+        let mut classifier = Classifier::with_labels(&["legal", "financial", "technical"]);
+
+        classifier.train(
+            "The court ruled in favor of the plaintiff in the lawsuit.",
+            "legal",
+        );
+        classifier.train(
+            "The judge signed the contract and dismissed the case.",
+            "legal",
+        );
+        classifier.train(
+            "The stock market rallied after the earnings report.",
+            "financial",
+        );
+        classifier.train(
+            "Investors reviewed the quarterly budget and revenue report.",
+            "financial",
+        );
+        classifier.train(
+            "The server crashed after a memory leak in the kernel.",
+            "technical",
+        );
+        classifier.train(
+            "The compiler failed to build the updated codebase.",
+            "technical",
+        );
+
+        assert_eq!(
+            classifier.predict("The judge dismissed the lawsuit after reviewing the contract."),
+            "legal"
+        );
+        assert_eq!(
+            classifier.predict("The company reported strong quarterly revenue growth."),
+            "financial"
+        );
+        assert_eq!(
+            classifier.predict("The build failed because of a kernel memory leak."),
+            "technical"
+        );
+    }
+
     #[test]
     fn test_new_from_pre_trained() -> Result<(), io::Error> {
         // Identify a typical spam message
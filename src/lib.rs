@@ -63,13 +63,15 @@
 //!     // Create a new classifier with an empty model
 //!     let mut classifier = Classifier::new();
 //!
-//!     // Train the classifier with a new spam example
-//!     let spam = "Don't forget our special promotion: -30% on men shoes, only today!";
-//!     classifier.train_spam(spam);
-//!
-//!     // Train the classifier with a new ham example
-//!     let ham = "Hi Bob, don't forget our meeting today at 4pm.";
-//!     classifier.train_ham(ham);
+//!     // Train the classifier with a few spam examples
+//!     classifier.train_spam("Don't forget our special promotion: -30% on men shoes, only today!");
+//!     classifier.train_spam("Buy cheap viagra now, special promotion, limited offer!");
+//!     classifier.train_spam("Win a free iphone now, click this link to claim your prize!");
+//!
+//!     // Train the classifier with a few ham examples
+//!     classifier.train_ham("Hi Bob, don't forget our meeting today at 4pm.");
+//!     classifier.train_ham("Can you send me the report before the end of the day?");
+//!     classifier.train_ham("Thanks for your help yesterday, see you at the meeting.");
 //!
 //!     // Identify a typical spam message
 //!     let spam = "Lose up to 19% weight. Special promotion on our new weightloss.";